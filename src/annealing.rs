@@ -0,0 +1,159 @@
+/// A tiny self-contained xorshift PRNG.
+pub struct Rng(u64);
+
+impl Rng {
+  pub fn new(seed: u64) -> Rng {
+    Rng(seed | 1)
+  }
+
+  fn next_u64(&mut self) -> u64 {
+    let mut x = self.0;
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    self.0 = x;
+    x
+  }
+
+  pub fn next_f64(&mut self) -> f64 {
+    (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+  }
+
+  pub fn gen_range(&mut self, n: usize) -> usize {
+    (self.next_u64() % n as u64) as usize
+  }
+}
+
+/// Proposes a neighbor by swapping two random positions in `candidate`.
+pub fn swap_two_positions<T>(candidate: &mut [T], rng: &mut Rng) {
+  let n = candidate.len();
+  if n < 2 {
+    return;
+  }
+  let i = rng.gen_range(n);
+  let j = rng.gen_range(n);
+  candidate.swap(i, j);
+}
+
+/// Proposes a neighbor by reversing a random sub-segment of `candidate`.
+pub fn reverse_random_segment<T>(candidate: &mut [T], rng: &mut Rng) {
+  let n = candidate.len();
+  if n < 2 {
+    return;
+  }
+  let i = rng.gen_range(n);
+  let j = rng.gen_range(n);
+  let (lo, hi) = if i <= j { (i, j) } else { (j, i) };
+  candidate[lo..=hi].reverse();
+}
+
+/// The fixed iteration budget and temperature schedule a run of `anneal`
+/// should use; there is no wall-clock limit. The temperature decays
+/// geometrically from
+/// `start_temperature` to `end_temperature` over `iterations` steps.
+pub struct AnnealingParams {
+  pub start_temperature: f64,
+  pub end_temperature: f64,
+  pub iterations: usize,
+}
+
+/// Simulated-annealing search over permutations (or any `Vec<T>`
+/// candidate), for ordering problems too large to search exhaustively.
+///
+/// `score` evaluates a candidate; higher is better. `neighbor` proposes a
+/// move from the current candidate (e.g. `swap_two_positions` or
+/// `reverse_random_segment`). Worsening moves are accepted with
+/// probability `exp(delta / temperature)`, so the search can escape local
+/// optima early on and settles down as the temperature cools. The
+/// best-scoring candidate seen across the whole run is returned, not
+/// just wherever the walk ends up.
+pub fn anneal<T, F, N>(
+  initial: Vec<T>,
+  score: F,
+  neighbor: N,
+  params: AnnealingParams,
+  rng: &mut Rng,
+) -> Vec<T>
+where
+  T: Clone,
+  F: Fn(&[T]) -> f64,
+  N: Fn(&mut [T], &mut Rng),
+{
+  let mut current = initial;
+  let mut current_score = score(&current);
+  let mut best = current.clone();
+  let mut best_score = current_score;
+  let cooling_ratio = params.end_temperature / params.start_temperature;
+
+  for step in 0..params.iterations {
+    let progress = step as f64 / params.iterations.max(1) as f64;
+    let temperature = params.start_temperature * cooling_ratio.powf(progress);
+
+    let mut candidate = current.clone();
+    neighbor(&mut candidate, rng);
+    let candidate_score = score(&candidate);
+    let delta = candidate_score - current_score;
+
+    if delta >= 0.0 || rng.next_f64() < (delta / temperature).exp() {
+      current = candidate;
+      current_score = candidate_score;
+      if current_score > best_score {
+        best = current.clone();
+        best_score = current_score;
+      }
+    }
+  }
+
+  best
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_always_accepts_improvements() {
+    // A score that peaks at zero and a neighbor that only decrements: the
+    // walk reaches zero through improvements, may then accept worsening
+    // moves past it, but the best candidate seen stays at zero.
+    let params = AnnealingParams {
+      start_temperature: 10.0,
+      end_temperature: 0.01,
+      iterations: 200,
+    };
+    let mut rng = Rng::new(42);
+    let best = anneal(
+      vec![5i64],
+      |candidate: &[i64]| -candidate[0].abs() as f64,
+      |candidate: &mut [i64], _rng: &mut Rng| candidate[0] -= 1,
+      params,
+      &mut rng,
+    );
+    assert_eq!(best, vec![0]);
+  }
+
+  #[test]
+  fn test_finds_best_of_a_small_permutation_space() {
+    let target = vec![3, 1, 2];
+    let params = AnnealingParams {
+      start_temperature: 5.0,
+      end_temperature: 0.01,
+      iterations: 500,
+    };
+    let mut rng = Rng::new(7);
+    let best = anneal(
+      vec![1, 2, 3],
+      |candidate: &[i64]| {
+        -candidate
+          .iter()
+          .zip(target.iter())
+          .map(|(a, b)| (a - b).abs())
+          .sum::<i64>() as f64
+      },
+      swap_two_positions,
+      params,
+      &mut rng,
+    );
+    assert_eq!(best, target);
+  }
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::fs;
 
 fn contains(x: i32, a: i32, b: i32) -> bool {
@@ -134,14 +135,149 @@ where
     .collect()
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Wire {
+  A,
+  B,
+}
+
+impl Wire {
+  fn opposite(self) -> Wire {
+    match self {
+      Wire::A => Wire::B,
+      Wire::B => Wire::A,
+    }
+  }
+}
+
+// A sweep covers a horizontal segment for every x between its two
+// events, so ties at the same x are ordered Start, Vertical, End: a
+// segment is active for verticals crossing it anywhere in its span,
+// including at its own start/end column.
+enum SweepEvent {
+  HorizontalStart(Wire, Edge),
+  Vertical(Wire, Edge),
+  HorizontalEnd(Wire, Edge),
+}
+
+impl SweepEvent {
+  fn x(&self) -> i32 {
+    match self {
+      SweepEvent::HorizontalStart(_, edge) => edge.from.x.min(edge.to.x),
+      SweepEvent::HorizontalEnd(_, edge) => edge.from.x.max(edge.to.x),
+      SweepEvent::Vertical(_, edge) => edge.from.x,
+    }
+  }
+
+  fn order(&self) -> u8 {
+    match self {
+      SweepEvent::HorizontalStart(..) => 0,
+      SweepEvent::Vertical(..) => 1,
+      SweepEvent::HorizontalEnd(..) => 2,
+    }
+  }
+}
+
+fn sweep_events(wire: Wire, edges: &[Edge]) -> Vec<SweepEvent> {
+  edges
+    .iter()
+    .flat_map(|edge| -> Vec<SweepEvent> {
+      if edge.horizontal() {
+        vec![
+          SweepEvent::HorizontalStart(wire, edge.clone()),
+          SweepEvent::HorizontalEnd(wire, edge.clone()),
+        ]
+      } else {
+        vec![SweepEvent::Vertical(wire, edge.clone())]
+      }
+    })
+    .collect()
+}
+
+/// Horizontal segments of each wire, active while the sweep is between
+/// their start and end column, keyed by y so a vertical can look up the
+/// ones it might cross with a range query.
+struct ActiveSets {
+  a: BTreeMap<i32, Vec<Edge>>,
+  b: BTreeMap<i32, Vec<Edge>>,
+}
+
+impl ActiveSets {
+  fn new() -> ActiveSets {
+    ActiveSets {
+      a: BTreeMap::new(),
+      b: BTreeMap::new(),
+    }
+  }
+
+  fn of(&self, wire: Wire) -> &BTreeMap<i32, Vec<Edge>> {
+    match wire {
+      Wire::A => &self.a,
+      Wire::B => &self.b,
+    }
+  }
+
+  fn of_mut(&mut self, wire: Wire) -> &mut BTreeMap<i32, Vec<Edge>> {
+    match wire {
+      Wire::A => &mut self.a,
+      Wire::B => &mut self.b,
+    }
+  }
+
+  fn insert(&mut self, wire: Wire, edge: Edge) {
+    self
+      .of_mut(wire)
+      .entry(edge.from.y)
+      .or_default()
+      .push(edge);
+  }
+
+  fn remove(&mut self, wire: Wire, edge: &Edge) {
+    let set = self.of_mut(wire);
+    if let Some(edges_at_y) = set.get_mut(&edge.from.y) {
+      if let Some(index) = edges_at_y.iter().position(|active| active == edge) {
+        edges_at_y.remove(index);
+      }
+      if edges_at_y.is_empty() {
+        set.remove(&edge.from.y);
+      }
+    }
+  }
+}
+
+// Sweeps the x-axis once, pairing each wire's horizontal segments
+// against the other wire's verticals via a y-keyed range query, instead
+// of comparing every edge of one wire against every edge of the other.
 fn crossings(circuit: &Circuit) -> Vec<Point> {
   let (a, b) = circuit;
-  b.into_iter()
-    .flat_map(|edge_b| {
-      a.into_iter()
-        .filter_map(move |edge_a| edge_b.intersect(&edge_a))
-    })
-    .skip(1) // All wires start in 0,0
+  let mut events = sweep_events(Wire::A, a);
+  events.extend(sweep_events(Wire::B, b));
+  events.sort_by_key(|event| (event.x(), event.order()));
+
+  let mut active = ActiveSets::new();
+  let mut crossings = Vec::new();
+
+  for event in events {
+    match event {
+      SweepEvent::HorizontalStart(wire, edge) => active.insert(wire, edge),
+      SweepEvent::HorizontalEnd(wire, edge) => active.remove(wire, &edge),
+      SweepEvent::Vertical(wire, vertical) => {
+        let y_lo = vertical.from.y.min(vertical.to.y);
+        let y_hi = vertical.from.y.max(vertical.to.y);
+        for edges_at_y in active.of(wire.opposite()).range(y_lo..=y_hi).map(|(_, e)| e) {
+          for horizontal in edges_at_y {
+            if let Some(point) = horizontal.intersect(&vertical) {
+              crossings.push(point);
+            }
+          }
+        }
+      }
+    }
+  }
+
+  crossings
+    .into_iter()
+    .filter(|point| !(point.x == 0 && point.y == 0)) // All wires start in 0,0
     .collect()
 }
 
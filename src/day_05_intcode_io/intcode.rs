@@ -1,12 +1,16 @@
+use std::collections::VecDeque;
+
 enum ParameterType {
   Position = 0,
   Immediate = 1,
+  Relative = 2,
 }
 
 impl ParameterType {
   fn from(digit: u8) -> ParameterType {
     match digit {
       1 => ParameterType::Immediate,
+      2 => ParameterType::Relative,
       _ => ParameterType::Position,
     }
   }
@@ -19,21 +23,6 @@ struct Instruction {
   param3: ParameterType,
 }
 
-fn lookup(memory: &[i64], address: usize, parameter_type: ParameterType) -> i64 {
-  match parameter_type {
-    ParameterType::Position => {
-      let lvalue = memory[address] as usize;
-      memory[lvalue]
-    }
-    ParameterType::Immediate => memory[address],
-  }
-}
-
-fn set(memory: &mut [i64], address: usize, value: i64) {
-  let lvalue = memory[address] as usize;
-  memory[lvalue] = value;
-}
-
 fn get_digits(n: i64) -> [u8; 4] {
   [
     (n / 10000 % 10) as u8,
@@ -54,85 +43,204 @@ fn parse_instruction(value: i64) -> Instruction {
   }
 }
 
-pub fn interpret(memory: &mut [i64], answers: &[i64]) -> Vec<i64> {
-  let mut ip: usize = 0; // instruction pointer
-  let mut input = answers.into_iter();
-  let mut output = Vec::<i64>::new();
-  loop {
-    let Instruction {
-      opcode,
-      param1,
-      param2,
-      param3: _param3, // Not yet used, but teased in the description
-    } = parse_instruction(memory[ip]);
-    match opcode {
-      // add
-      1 => {
-        set(
-          memory,
-          ip + 3,
-          lookup(memory, ip + 1, param1) + lookup(memory, ip + 2, param2),
-        );
-        ip += 4;
-      }
-      // multiply
-      2 => {
-        set(
-          memory,
-          ip + 3,
-          lookup(memory, ip + 1, param1) * lookup(memory, ip + 2, param2),
-        );
-        ip += 4;
-      }
-      // read input
-      3 => {
-        let value = *input.next().expect("Not enough input provided");
-        set(memory, ip + 1, value);
-        ip += 2;
+/// What `IntcodeVm::run` yielded before suspending or halting.
+#[derive(Debug, PartialEq)]
+pub enum VmState {
+  NeedInput,
+  Output(i64),
+  Halted,
+}
+
+/// The I/O surface an `IntcodeVm` is driven through. Opcode 3 calls the
+/// non-blocking `try_recv`, suspending on `None` instead of panicking, so
+/// a machine can be driven as a coroutine. `recv` is the blocking
+/// counterpart for callers that want to drive a machine synchronously:
+/// it retries/collects until an input is ready, or panics if its source
+/// can never produce one.
+pub trait IoClient {
+  fn recv(&mut self) -> i64;
+  fn try_recv(&mut self) -> Option<i64>;
+}
+
+/// An `IoClient` backed by a plain queue of pre-computed inputs.
+pub struct VecIoClient {
+  inputs: VecDeque<i64>,
+}
+
+impl VecIoClient {
+  pub fn new(inputs: &[i64]) -> VecIoClient {
+    VecIoClient {
+      inputs: inputs.iter().copied().collect(),
+    }
+  }
+
+  /// Queues up an additional input.
+  pub fn push(&mut self, value: i64) {
+    self.inputs.push_back(value);
+  }
+}
+
+impl IoClient for VecIoClient {
+  fn recv(&mut self) -> i64 {
+    self.inputs.pop_front().expect("Not enough input provided")
+  }
+
+  fn try_recv(&mut self) -> Option<i64> {
+    self.inputs.pop_front()
+  }
+}
+
+/// A resumable Intcode machine. `memory` grows (zero-filled) on any
+/// access past its current length.
+pub struct IntcodeVm {
+  memory: Vec<i64>,
+  ip: usize,
+  relative_base: i64,
+}
+
+impl IntcodeVm {
+  pub fn new(memory: Vec<i64>) -> IntcodeVm {
+    IntcodeVm {
+      memory,
+      ip: 0,
+      relative_base: 0,
+    }
+  }
+
+  fn ensure_len(&mut self, len: usize) {
+    if self.memory.len() < len {
+      self.memory.resize(len, 0);
+    }
+  }
+
+  fn lookup(&mut self, address: usize, parameter_type: ParameterType) -> i64 {
+    self.ensure_len(address + 1);
+    match parameter_type {
+      ParameterType::Position => {
+        let lvalue = self.memory[address] as usize;
+        self.ensure_len(lvalue + 1);
+        self.memory[lvalue]
       }
-      // output
-      4 => {
-        let value = lookup(memory, ip + 1, param1);
-        println!("{}", value);
-        output.push(value);
-        ip += 2;
+      ParameterType::Immediate => self.memory[address],
+      ParameterType::Relative => {
+        let lvalue = (self.memory[address] + self.relative_base) as usize;
+        self.ensure_len(lvalue + 1);
+        self.memory[lvalue]
       }
-      // jump-if-true
-      5 => {
-        if lookup(memory, ip + 1, param1) != 0 {
-          ip = lookup(memory, ip + 2, param2) as usize;
-        } else {
-          ip += 3
+    }
+  }
+
+  fn set(&mut self, address: usize, parameter_type: ParameterType, value: i64) {
+    self.ensure_len(address + 1);
+    let lvalue = match parameter_type {
+      ParameterType::Relative => (self.memory[address] + self.relative_base) as usize,
+      _ => self.memory[address] as usize,
+    };
+    self.ensure_len(lvalue + 1);
+    self.memory[lvalue] = value;
+  }
+
+  /// Runs until the machine needs input, produces an output, or halts.
+  pub fn run(&mut self, io: &mut dyn IoClient) -> VmState {
+    loop {
+      self.ensure_len(self.ip + 1);
+      let Instruction {
+        opcode,
+        param1,
+        param2,
+        param3,
+      } = parse_instruction(self.memory[self.ip]);
+      match opcode {
+        // add
+        1 => {
+          let value = self.lookup(self.ip + 1, param1) + self.lookup(self.ip + 2, param2);
+          self.set(self.ip + 3, param3, value);
+          self.ip += 4;
         }
-      }
-      // jump-if-false
-      6 => {
-        if lookup(memory, ip + 1, param1) == 0 {
-          ip = lookup(memory, ip + 2, param2) as usize;
-        } else {
-          ip += 3
+        // multiply
+        2 => {
+          let value = self.lookup(self.ip + 1, param1) * self.lookup(self.ip + 2, param2);
+          self.set(self.ip + 3, param3, value);
+          self.ip += 4;
         }
+        // read input
+        3 => match io.try_recv() {
+          Some(value) => {
+            self.set(self.ip + 1, param1, value);
+            self.ip += 2;
+          }
+          None => return VmState::NeedInput,
+        },
+        // output
+        4 => {
+          let value = self.lookup(self.ip + 1, param1);
+          self.ip += 2;
+          return VmState::Output(value);
+        }
+        // jump-if-true
+        5 => {
+          if self.lookup(self.ip + 1, param1) != 0 {
+            self.ip = self.lookup(self.ip + 2, param2) as usize;
+          } else {
+            self.ip += 3
+          }
+        }
+        // jump-if-false
+        6 => {
+          if self.lookup(self.ip + 1, param1) == 0 {
+            self.ip = self.lookup(self.ip + 2, param2) as usize;
+          } else {
+            self.ip += 3
+          }
+        }
+        // less-than
+        7 => {
+          let lt = self.lookup(self.ip + 1, param1) < self.lookup(self.ip + 2, param2);
+          self.set(self.ip + 3, param3, if lt { 1 } else { 0 });
+          self.ip += 4
+        }
+        // equals
+        8 => {
+          let eq = self.lookup(self.ip + 1, param1) == self.lookup(self.ip + 2, param2);
+          self.set(self.ip + 3, param3, if eq { 1 } else { 0 });
+          self.ip += 4
+        }
+        // adjust relative base
+        9 => {
+          self.relative_base += self.lookup(self.ip + 1, param1);
+          self.ip += 2;
+        }
+        // exit
+        99 => return VmState::Halted,
+        _ => panic!("Unknown opcode {} at address {}", opcode, self.ip),
       }
-      // less-than
-      7 => {
-        let lt = lookup(memory, ip + 1, param1) < lookup(memory, ip + 2, param2);
-        set(memory, ip + 3, if lt { 1 } else { 0 });
-        ip += 4
-      }
-      // equals
-      8 => {
-        let eq = lookup(memory, ip + 1, param1) == lookup(memory, ip + 2, param2);
-        set(memory, ip + 3, if eq { 1 } else { 0 });
-        ip += 4
+    }
+  }
+}
+
+/// Runs a program to completion against a fixed set of inputs, the way
+/// the old one-shot interpreter did.
+pub fn interpret(memory: &mut [i64], answers: &[i64]) -> Vec<i64> {
+  let mut vm = IntcodeVm::new(memory.to_vec());
+  let mut io = VecIoClient::new(answers);
+  let mut output = Vec::new();
+
+  loop {
+    match vm.run(&mut io) {
+      VmState::Output(value) => {
+        println!("{}", value);
+        output.push(value);
       }
-      // exit
-      99 => {
-        return output;
-        // ip += 1;
+      VmState::Halted => break,
+      VmState::NeedInput => {
+        let value = io.recv();
+        io.push(value);
       }
-      _ => panic!("Unknown opcode {} at address {}", opcode, ip),
     }
   }
+
+  memory.copy_from_slice(&vm.memory[..memory.len()]);
+  output
 }
 
 #[cfg(test)]
@@ -243,4 +351,75 @@ mod tests {
     let output = interpret(&mut memory, &[100]);
     assert_eq!(output, [1001]);
   }
+
+  #[test]
+  fn test_resumable_needs_input() {
+    let mut vm = IntcodeVm::new(vec![3, 0, 99]);
+    let mut io = VecIoClient::new(&[]);
+    assert_eq!(vm.run(&mut io), VmState::NeedInput);
+  }
+
+  #[test]
+  #[should_panic(expected = "Not enough input provided")]
+  fn test_interpret_panics_when_input_exhausted() {
+    let mut memory: &mut [i64] = &mut [3, 0, 99];
+    interpret(&mut memory, &[]);
+  }
+
+  #[test]
+  fn test_vec_io_client_recv() {
+    let mut io = VecIoClient::new(&[7, 8]);
+    assert_eq!(io.recv(), 7);
+    assert_eq!(io.recv(), 8);
+  }
+
+  #[test]
+  fn test_resumable_output_then_halt() {
+    let mut vm = IntcodeVm::new(vec![4, 3, 99, 42]);
+    let mut io = VecIoClient::new(&[]);
+    assert_eq!(vm.run(&mut io), VmState::Output(42));
+    assert_eq!(vm.run(&mut io), VmState::Halted);
+  }
+
+  fn run_to_completion(program: Vec<i64>) -> Vec<i64> {
+    let mut vm = IntcodeVm::new(program);
+    let mut io = VecIoClient::new(&[]);
+    let mut output = Vec::new();
+    loop {
+      match vm.run(&mut io) {
+        VmState::Output(value) => output.push(value),
+        VmState::Halted => return output,
+        VmState::NeedInput => panic!("Not enough input provided"),
+      }
+    }
+  }
+
+  #[test]
+  fn test_quine() {
+    let quine = vec![
+      109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+    ];
+    assert_eq!(run_to_completion(quine.clone()), quine);
+  }
+
+  #[test]
+  fn test_large_multiplication_output() {
+    let output = run_to_completion(vec![1102, 34915192, 34915192, 7, 4, 7, 99, 0]);
+    assert_eq!(output.len(), 1);
+    assert_eq!(output[0].to_string().len(), 16);
+  }
+
+  #[test]
+  fn test_large_immediate_output() {
+    assert_eq!(
+      run_to_completion(vec![104, 1125899906842624, 99]),
+      [1125899906842624]
+    );
+  }
+
+  #[test]
+  fn test_memory_grows_past_source_length() {
+    let mut vm = IntcodeVm::new(vec![1101, 1, 1, 3, 99]);
+    assert_eq!(vm.lookup(10, ParameterType::Immediate), 0);
+  }
 }
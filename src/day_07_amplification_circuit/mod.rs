@@ -0,0 +1,182 @@
+use crate::annealing::{self, AnnealingParams, Rng};
+use crate::day_05_intcode_io::intcode::{IntcodeVm, VecIoClient, VmState};
+
+/// N copies of the same program wired into a pipeline, each machine's
+/// output feeding the next machine's input.
+pub struct AmplifierController {
+  vms: Vec<IntcodeVm>,
+  io: Vec<VecIoClient>,
+}
+
+impl AmplifierController {
+  pub fn new(program: &[i64], phases: &[i64]) -> AmplifierController {
+    AmplifierController {
+      vms: phases.iter().map(|_| IntcodeVm::new(program.to_vec())).collect(),
+      io: phases.iter().map(|&phase| VecIoClient::new(&[phase])).collect(),
+    }
+  }
+
+  /// Feeds `signal` through the amplifiers once, first to last, and
+  /// returns the last amplifier's output.
+  pub fn run_single_pass(&mut self, signal: i64) -> i64 {
+    let mut signal = signal;
+    for (vm, io) in self.vms.iter_mut().zip(self.io.iter_mut()) {
+      io.push(signal);
+      signal = match vm.run(io) {
+        VmState::Output(value) => value,
+        other => panic!("Amplifier produced unexpected state: {:?}", other),
+      };
+    }
+    signal
+  }
+
+  /// Runs the pipeline as a closed loop, routing the last amplifier's
+  /// output back into the first amplifier's input, until every machine
+  /// has halted. Returns the final output.
+  pub fn run_feedback_loop(&mut self, signal: i64) -> i64 {
+    let mut signal = signal;
+    let mut halted = vec![false; self.vms.len()];
+    while !halted.iter().all(|&h| h) {
+      for ((vm, io), halted) in self.vms.iter_mut().zip(self.io.iter_mut()).zip(halted.iter_mut()) {
+        if *halted {
+          continue;
+        }
+        io.push(signal);
+        match vm.run(io) {
+          VmState::Output(value) => signal = value,
+          VmState::Halted => *halted = true,
+          VmState::NeedInput => panic!("Amplifier needs more input than the loop provided"),
+        }
+      }
+    }
+    signal
+  }
+}
+
+/// Generates every permutation of `items` in place using Heap's algorithm.
+fn permutations(items: &[i64]) -> Vec<Vec<i64>> {
+  let n = items.len();
+  let mut current = items.to_vec();
+  let mut permutations = vec![current.clone()];
+  let mut c = vec![0usize; n];
+  let mut i = 0;
+  while i < n {
+    if c[i] < i {
+      if i % 2 == 0 {
+        current.swap(0, i);
+      } else {
+        current.swap(c[i], i);
+      }
+      permutations.push(current.clone());
+      c[i] += 1;
+      i = 0;
+    } else {
+      c[i] = 0;
+      i += 1;
+    }
+  }
+  permutations
+}
+
+/// Tries every permutation of `phases` across a fresh `AmplifierController`
+/// and returns the largest thrust signal any ordering produces. Runs the
+/// feedback loop in every case, which also covers the non-looping case
+/// where a program halts after its first output.
+pub fn maximize_thrust(program: &[i64], phases: &[i64]) -> i64 {
+  permutations(phases)
+    .into_iter()
+    .map(|phases| AmplifierController::new(program, &phases).run_feedback_loop(0))
+    .max()
+    .expect("phases must not be empty")
+}
+
+fn thrust_for(program: &[i64], phases: &[i64]) -> i64 {
+  AmplifierController::new(program, phases).run_feedback_loop(0)
+}
+
+/// Simulated-annealing alternative to `maximize_thrust`, for amplifier
+/// counts too large to search exhaustively. Searches the permutation
+/// space of `phases` with a fixed annealing budget and returns the best
+/// thrust signal found rather than the guaranteed optimum.
+///
+/// `seed` drives the search's randomness, so a given program/phases/seed
+/// always returns the same result instead of gambling on entropy.
+pub fn anneal_thrust(program: &[i64], phases: &[i64], seed: u64) -> i64 {
+  let mut rng = Rng::new(seed);
+  let params = AnnealingParams {
+    start_temperature: 100.0,
+    end_temperature: 0.01,
+    iterations: 2000,
+  };
+  let best_phases = annealing::anneal(
+    phases.to_vec(),
+    |candidate| thrust_for(program, candidate) as f64,
+    |candidate, rng| {
+      if rng.next_f64() < 0.5 {
+        annealing::swap_two_positions(candidate, rng);
+      } else {
+        annealing::reverse_random_segment(candidate, rng);
+      }
+    },
+    params,
+    &mut rng,
+  );
+  thrust_for(program, &best_phases)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SINGLE_PASS_PROGRAM: [i64; 17] = [
+    3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+  ];
+
+  const FEEDBACK_LOOP_PROGRAM: [i64; 29] = [
+    3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1, 28, 1005,
+    28, 6, 99, 0, 0, 5,
+  ];
+
+  #[test]
+  fn test_permutations_cover_every_ordering() {
+    let perms = permutations(&[0, 1, 2]);
+    assert_eq!(perms.len(), 6);
+    assert_eq!(perms[0], vec![0, 1, 2]);
+  }
+
+  #[test]
+  fn test_single_pass_amplifiers() {
+    let mut controller = AmplifierController::new(&SINGLE_PASS_PROGRAM, &[4, 3, 2, 1, 0]);
+    assert_eq!(controller.run_single_pass(0), 43210);
+  }
+
+  #[test]
+  fn test_maximize_thrust_single_pass() {
+    assert_eq!(
+      maximize_thrust(&SINGLE_PASS_PROGRAM, &[0, 1, 2, 3, 4]),
+      43210
+    );
+  }
+
+  #[test]
+  fn test_feedback_loop_amplifiers() {
+    let mut controller = AmplifierController::new(&FEEDBACK_LOOP_PROGRAM, &[9, 8, 7, 6, 5]);
+    assert_eq!(controller.run_feedback_loop(0), 139629729);
+  }
+
+  #[test]
+  fn test_maximize_thrust_feedback_loop() {
+    assert_eq!(
+      maximize_thrust(&FEEDBACK_LOOP_PROGRAM, &[5, 6, 7, 8, 9]),
+      139629729
+    );
+  }
+
+  #[test]
+  fn test_anneal_thrust_matches_exhaustive_search() {
+    assert_eq!(
+      anneal_thrust(&FEEDBACK_LOOP_PROGRAM, &[5, 6, 7, 8, 9], 42),
+      139629729
+    );
+  }
+}